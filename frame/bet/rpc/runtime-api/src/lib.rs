@@ -0,0 +1,56 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition for the `bet` pallet.
+//!
+//! This lets a client ask what a bettor would receive and when their stake unlocks without
+//! having to submit (and pay for) a `collect` extrinsic just to find out.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+sp_api::decl_runtime_api! {
+	/// The API to query a bettor's position valuation, projected payout and liquidity status,
+	/// plus the pot backing a given asset.
+	pub trait BetApi<AccountId, AssetId, Balance, BlockNumber> where
+		AccountId: codec::Codec,
+		AssetId: codec::Codec,
+		Balance: codec::Codec,
+		BlockNumber: codec::Codec,
+	{
+		/// The amount actually staked in position `position_id`, as of its last consolidation —
+		/// unlike `projected_balance`, this doesn't project any reward accrued since then.
+		fn position_value(account: AccountId, position_id: u32) -> Balance;
+
+		/// The reward position `position_id` has accrued since its last consolidation, were
+		/// `collect` called right now: `projected_balance - position_value`.
+		fn pending_payout(account: AccountId, position_id: u32) -> Balance;
+
+		/// The balance `account` would receive from position `position_id` if they called
+		/// `collect` in the current block.
+		fn projected_balance(account: AccountId, position_id: u32) -> Balance;
+
+		/// The period index (compared against the pallet's `Index`, not a block number) from
+		/// which position `position_id` is unlocked, if it has a lock set.
+		fn unlock_block(account: AccountId, position_id: u32) -> Option<BlockNumber>;
+
+		/// Whether position `position_id` is fully liquid right now, i.e. `collect` would
+		/// actually withdraw it rather than being a no-op.
+		fn is_liquid(account: AccountId, position_id: u32) -> bool;
+
+		/// The pot currently backing payouts in `asset`.
+		fn pot_total(asset: AssetId) -> Balance;
+	}
+}
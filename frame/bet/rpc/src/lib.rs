@@ -0,0 +1,192 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node-side RPC implementation for the `bet` pallet's `BetApi` runtime API, so dashboards and
+//! wallets can show a bettor's live position valuation, projected payout, unlock countdown and
+//! liquidity status, plus an asset's current pot, without submitting a transaction.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpsee::{
+	core::{async_trait, RpcResult},
+	proc_macros::rpc,
+	types::error::ErrorObject,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_bet_rpc_runtime_api::BetApi as BetRuntimeApi;
+
+/// The RPC surface exposed to clients for querying a bettor's position valuation, projected
+/// payout and liquidity status, plus an asset's pot.
+#[rpc(client, server)]
+pub trait BetApi<BlockHash, AccountId, AssetId, Balance, BlockNumber> {
+	/// The amount actually staked in position `position_id`, as of its last consolidation —
+	/// unlike `bet_projectedBalance`, this doesn't project any reward accrued since then.
+	#[method(name = "bet_positionValue")]
+	fn position_value(
+		&self,
+		account: AccountId,
+		position_id: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Balance>;
+
+	/// The reward position `position_id` has accrued since its last consolidation, were
+	/// `collect` called right now.
+	#[method(name = "bet_pendingPayout")]
+	fn pending_payout(
+		&self,
+		account: AccountId,
+		position_id: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Balance>;
+
+	/// The balance `account` would receive from position `position_id` if they called `collect`
+	/// right now.
+	#[method(name = "bet_projectedBalance")]
+	fn projected_balance(
+		&self,
+		account: AccountId,
+		position_id: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Balance>;
+
+	/// The period index (compared against the pallet's `Index`, not a block number) from which
+	/// position `position_id` is unlocked, if it has a lock set.
+	#[method(name = "bet_unlockBlock")]
+	fn unlock_block(
+		&self,
+		account: AccountId,
+		position_id: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<BlockNumber>>;
+
+	/// Whether position `position_id` is fully liquid right now, i.e. `collect` would actually
+	/// withdraw it rather than being a no-op.
+	#[method(name = "bet_isLiquid")]
+	fn is_liquid(
+		&self,
+		account: AccountId,
+		position_id: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<bool>;
+
+	/// The pot currently backing payouts in `asset`.
+	#[method(name = "bet_potTotal")]
+	fn pot_total(&self, asset: AssetId, at: Option<BlockHash>) -> RpcResult<Balance>;
+}
+
+/// A struct that implements the [`BetApiServer`].
+pub struct Bet<C, P> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<P>,
+}
+
+impl<C, P> Bet<C, P> {
+	/// Create a new instance of the `Bet` RPC helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error code used when the runtime fails to answer a `bet_*` RPC call.
+const RUNTIME_ERROR: i32 = 1;
+
+fn runtime_error(err: impl std::fmt::Debug) -> ErrorObject<'static> {
+	ErrorObject::owned(RUNTIME_ERROR, "Unable to query bet projection", Some(format!("{:?}", err)))
+}
+
+#[async_trait]
+impl<C, Block, AccountId, AssetId, Balance, BlockNumber>
+	BetApiServer<<Block as BlockT>::Hash, AccountId, AssetId, Balance, BlockNumber> for Bet<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: BetRuntimeApi<Block, AccountId, AssetId, Balance, BlockNumber>,
+	AccountId: Codec,
+	AssetId: Codec,
+	Balance: Codec,
+	BlockNumber: Codec,
+{
+	fn position_value(
+		&self,
+		account: AccountId,
+		position_id: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.position_value(&at, account, position_id).map_err(runtime_error)
+	}
+
+	fn pending_payout(
+		&self,
+		account: AccountId,
+		position_id: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.pending_payout(&at, account, position_id).map_err(runtime_error)
+	}
+
+	fn projected_balance(
+		&self,
+		account: AccountId,
+		position_id: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.projected_balance(&at, account, position_id).map_err(runtime_error)
+	}
+
+	fn unlock_block(
+		&self,
+		account: AccountId,
+		position_id: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<BlockNumber>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.unlock_block(&at, account, position_id).map_err(runtime_error)
+	}
+
+	fn is_liquid(
+		&self,
+		account: AccountId,
+		position_id: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<bool> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.is_liquid(&at, account, position_id).map_err(runtime_error)
+	}
+
+	fn pot_total(&self, asset: AssetId, at: Option<<Block as BlockT>::Hash>) -> RpcResult<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.pot_total(&at, asset).map_err(runtime_error)
+	}
+}
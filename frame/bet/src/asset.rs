@@ -0,0 +1,70 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The single point of contact with the underlying multi-asset backend, so the rest of the
+//! pallet never has to know whether "committed to a bet" is a lock, a hold, or something else.
+
+use frame_support::traits::tokens::{
+	fungibles::{Inspect, InspectHold, MutateHold},
+	Fortitude, Precision, Preservation,
+};
+
+use crate::{BalanceOf, HeldReason, Trait};
+
+/// Namespace for every call the pallet makes into `Trait::Assets`.
+#[allow(unused)]
+pub(crate) struct Asset<T>(sp_std::marker::PhantomData<T>);
+
+impl<T: Trait> Asset<T> {
+	/// The portion of `who`'s balance in `asset` that isn't already held and so can be committed
+	/// to a new or larger bet.
+	pub(crate) fn stakeable_balance(asset: T::AssetId, who: &T::AccountId) -> BalanceOf<T> {
+		T::Assets::reducible_balance(asset, who, Preservation::Preserve, Fortitude::Polite)
+	}
+
+	/// The amount of `who`'s balance in `asset` currently held for staking.
+	pub(crate) fn held_stake(asset: T::AssetId, who: &T::AccountId) -> BalanceOf<T> {
+		T::Assets::balance_on_hold(asset, &HeldReason::Staked.into(), who)
+	}
+
+	/// Commit `amount` of `who`'s free balance in `asset` to their bet. Only the staked amount
+	/// becomes immobile; the rest of their balance stays transferable.
+	pub(crate) fn hold_stake(asset: T::AssetId, who: &T::AccountId, amount: BalanceOf<T>) -> frame_support::dispatch::DispatchResult {
+		T::Assets::hold(asset, &HeldReason::Staked.into(), who, amount)
+	}
+
+	/// Release `amount` of previously held `asset` stake back into `who`'s transferable balance.
+	pub(crate) fn release_stake(asset: T::AssetId, who: &T::AccountId, amount: BalanceOf<T>) -> frame_support::dispatch::DispatchResult {
+		T::Assets::release(asset, &HeldReason::Staked.into(), who, amount, Precision::BestEffort).map(|_| ())
+	}
+
+	/// Pay `amount` of winnings in `asset` into `who`'s held stake.
+	pub(crate) fn reward(asset: T::AssetId, who: &T::AccountId, amount: BalanceOf<T>) {
+		let _ = T::Assets::mint_into_held(asset, &HeldReason::Staked.into(), who, amount);
+	}
+
+	/// Burn `amount` of `asset` out of `who`'s held stake, e.g. on a wipeout.
+	pub(crate) fn confiscate(asset: T::AssetId, who: &T::AccountId, amount: BalanceOf<T>) {
+		let _ = T::Assets::burn_held(
+			asset,
+			&HeldReason::Staked.into(),
+			who,
+			amount,
+			Precision::BestEffort,
+			Fortitude::Force,
+		);
+	}
+}
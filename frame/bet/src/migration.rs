@@ -0,0 +1,73 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage migration from the old `LockableCurrency` lock to a `HeldReason::Staked` hold.
+//!
+//! Runtimes still carrying accounts with the old `MODULE_ID` lock from before this pallet
+//! switched to holds should run [`migrate_locks_to_holds`] once, from a `frame_support`
+//! `OnRuntimeUpgrade` hook, before live traffic resumes. Bets predating the move to multi-asset
+//! betting have no `asset_id` of their own, so they're assigned `T::AssetId::default()` — the
+//! runtime's native asset — in the same pass.
+//!
+//! This only carries forward the per-position `Bets` entries. It deliberately does *not* attempt
+//! to translate the pallet's old single-asset `Pot`/`Total`/`Incoming`/`Outgoing`/`Acc`/`AccAt`/
+//! `Wipeouts`/`SerpHistory` values into the native asset's slot of the new per-`AssetId` maps —
+//! those items changed storage shape (a single value, or a map keyed only by `BlockNumber`, to a
+//! map/double-map additionally keyed by `AssetId`), so the old values live under different
+//! storage keys and are simply orphaned by this upgrade. Runtimes should drain every open
+//! position (or accept that in-flight payout/wipeout history resets at the upgrade boundary)
+//! before flipping over; a from-scratch `Acc`/`AccAt` for the native asset is otherwise
+//! indistinguishable from "nothing has ever been staked yet".
+use frame_support::{traits::LockableCurrency, weights::Weight};
+use sp_runtime::{FixedU128, traits::One};
+
+use crate::{Asset, BalanceOf, Bets, HeldReason, Trait, MODULE_ID};
+
+/// Replace every outstanding `MODULE_ID` lock with an equivalent hold under
+/// `HeldReason::Staked` in the native asset, for every account that still has an entry in
+/// `Bets`, and register the native asset in `KnownAssets` so `on_finalize` picks up its
+/// settlement from the very next period.
+pub fn migrate_locks_to_holds<T: Trait>() -> Weight
+where
+	T::Currency: LockableCurrency<T::AccountId, Moment = T::BlockNumber>,
+{
+	let mut migrated: Weight = 0;
+	let mut handled_accounts = sp_std::collections::btree_set::BTreeSet::new();
+	let native = T::AssetId::default();
+	let mut any_bets = false;
+
+	for (who, position_id, mut betting) in <Bets<T>>::iter() {
+		let amount: BalanceOf<T> = betting.balance;
+		betting.asset_id = native;
+		// Pre-existing bets predate the vote-escrow lock-up commitment entirely, so they carry
+		// no weight boost of their own.
+		betting.weight = FixedU128::one();
+		<Bets<T>>::insert(&who, position_id, betting);
+		any_bets = true;
+
+		if handled_accounts.insert(who.clone()) {
+			T::Currency::remove_lock(MODULE_ID, &who);
+		}
+		let _ = Asset::<T>::hold_stake(native, &who, amount);
+		migrated += 1;
+	}
+
+	if any_bets {
+		crate::Module::<T>::note_known_asset(native);
+	}
+
+	migrated
+}
@@ -0,0 +1,63 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An on-chain commit-reveal price oracle, usable as `Trait::OneEuro`.
+//!
+//! Authorized reporters first `commit_price` to a value as only `hash(price, salt)`, hiding it
+//! from every other reporter until the period's reveal phase; they then `reveal_price` the
+//! `(price, salt)` pair, which is checked against the commitment on file and folded into
+//! `RevealedPrices`. This oracle's [`FetchPrice::fetch_price`] reports the median of the
+//! current period's revealed prices, so a single reporter — whether dishonest or merely
+//! unlucky — can't swing the sample alone, and a reporter can no longer see what anyone else
+//! submitted before deciding their own value, unlike a bare `set_price`-style write, which a
+//! reporter could always place last, having seen (or colluded on) everyone else's.
+//!
+//! Plugging this in as `Trait::OneEuro` changes nothing about how `on_finalize` samples or
+//! aggregates prices — it's called at exactly the same segment boundaries as any other
+//! `FetchPrice` implementation, and its samples pass through the same staleness/confidence
+//! filtering and median/TWAP aggregation as before.
+
+use frame_system as system;
+use sp_runtime::traits::Zero;
+
+use crate::{BalanceOf, FetchPrice, Module, PriceData, Trait};
+
+/// See the [module documentation](self).
+pub struct CommitRevealOracle<T>(sp_std::marker::PhantomData<T>);
+
+impl<T: Trait> FetchPrice<BalanceOf<T>, T::BlockNumber> for CommitRevealOracle<T> {
+	fn fetch_price() -> Option<PriceData<BalanceOf<T>, T::BlockNumber>> {
+		let mut prices = Module::<T>::revealed_prices(Module::<T>::index());
+		if prices.is_empty() {
+			return None;
+		}
+		prices.sort();
+		let n = prices.len();
+		let value = if n % 2 == 1 {
+			prices[n / 2]
+		} else {
+			(prices[n / 2 - 1] + prices[n / 2]) / 2u32.into()
+		};
+
+		Some(PriceData {
+			value,
+			observed_at: <system::Module<T>>::block_number(),
+			// Revealed prices are already checked against their commitment hash, so there's
+			// nothing further to bound a spread against — report maximal confidence.
+			confidence: Zero::zero(),
+		})
+	}
+}